@@ -0,0 +1,82 @@
+//! Minimal base64 (standard alphabet, with padding) codec used to convert
+//! between PEM and DER certificate encodings without pulling in an external
+//! dependency.
+
+use crate::Error;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = *chunk.get(1).unwrap_or(&0);
+        let b2 = *chunk.get(2).unwrap_or(&0);
+
+        out.push(ALPHABET[(b0 >> 2) as usize] as char);
+        out.push(ALPHABET[(((b0 & 0x03) << 4) | (b1 >> 4)) as usize] as char);
+        out.push(if chunk.len() > 1 {
+            ALPHABET[(((b1 & 0x0f) << 2) | (b2 >> 6)) as usize] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 {
+            ALPHABET[(b2 & 0x3f) as usize] as char
+        } else {
+            '='
+        });
+    }
+    out
+}
+
+fn decode_char(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+/// Decodes base64 text, ignoring any whitespace (including line breaks)
+/// interspersed in the input, as is common in PEM bodies.
+pub(crate) fn decode(data: &str) -> Result<Vec<u8>, Error> {
+    let filtered: Vec<u8> = data
+        .bytes()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+
+    if filtered.is_empty() {
+        return Err(Error::new_other("base64 input is empty"));
+    }
+
+    if !filtered.len().is_multiple_of(4) {
+        return Err(Error::new_other("base64 input has invalid length"));
+    }
+
+    let mut out = Vec::with_capacity(filtered.len() / 4 * 3);
+    for group in filtered.chunks(4) {
+        let mut values = [0u8; 4];
+        let mut pad = 0;
+        for (i, &c) in group.iter().enumerate() {
+            if c == b'=' {
+                pad += 1;
+                continue;
+            }
+            values[i] = decode_char(c)
+                .ok_or_else(|| Error::new_other("base64 input contains invalid character"))?;
+        }
+
+        out.push((values[0] << 2) | (values[1] >> 4));
+        if pad < 2 {
+            out.push((values[1] << 4) | (values[2] >> 2));
+        }
+        if pad < 1 {
+            out.push((values[2] << 6) | values[3]);
+        }
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,94 @@
+//! `tls-server-end-point` channel binding (RFC 5929), used to drive
+//! SASL/SCRAM `-PLUS` authentication.
+//!
+//! Per RFC 5929, the channel binding value is the digest of the peer's
+//! leaf certificate, hashed with the algorithm named in the certificate's
+//! own signature, except that MD5 and SHA-1 are substituted with SHA-256.
+
+use crate::Error;
+use crate::Result;
+use crate::sha256;
+use crate::sha512;
+
+// DER-encoded (tag-less) OIDs for the signature algorithms we recognize.
+const MD5_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x04];
+const SHA1_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x05];
+const SHA256_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+const SHA384_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c];
+const SHA512_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d];
+const ECDSA_WITH_SHA1: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x01];
+const ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const ECDSA_WITH_SHA384: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03];
+const ECDSA_WITH_SHA512: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x04];
+
+enum HashAlg {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+/// Computes the `tls-server-end-point` channel binding value for a peer
+/// leaf certificate in DER form.
+pub(crate) fn tls_server_end_point(der: &[u8]) -> Result<Vec<u8>> {
+    let oid = signature_algorithm_oid(der)?;
+    let alg = hash_alg_for_oid(&oid)?;
+    Ok(match alg {
+        HashAlg::Sha256 => sha256::digest(der),
+        HashAlg::Sha384 => sha512::digest_384(der),
+        HashAlg::Sha512 => sha512::digest_512(der),
+    })
+}
+
+fn hash_alg_for_oid(oid: &[u8]) -> Result<HashAlg> {
+    match oid {
+        // MD5 and SHA-1 are substituted with SHA-256, per RFC 5929 section 4.1.
+        MD5_WITH_RSA | SHA1_WITH_RSA | ECDSA_WITH_SHA1 => Ok(HashAlg::Sha256),
+        SHA256_WITH_RSA | ECDSA_WITH_SHA256 => Ok(HashAlg::Sha256),
+        SHA384_WITH_RSA | ECDSA_WITH_SHA384 => Ok(HashAlg::Sha384),
+        SHA512_WITH_RSA | ECDSA_WITH_SHA512 => Ok(HashAlg::Sha512),
+        _ => Err(Error::new_other(
+            "unrecognized certificate signature algorithm OID",
+        )),
+    }
+}
+
+/// A minimal DER reader: just enough to walk past `Certificate.tbsCertificate`
+/// and read the OID inside `Certificate.signatureAlgorithm`.
+fn signature_algorithm_oid(der: &[u8]) -> Result<Vec<u8>> {
+    let malformed = || Error::new_other("malformed certificate DER");
+
+    let (_, certificate_content, _) = read_tlv(der).ok_or_else(malformed)?;
+    let (_, _tbs_certificate, rest) = read_tlv(certificate_content).ok_or_else(malformed)?;
+    let (_, signature_algorithm, _) = read_tlv(rest).ok_or_else(malformed)?;
+    let (tag, oid, _) = read_tlv(signature_algorithm).ok_or_else(malformed)?;
+    if tag != 0x06 {
+        return Err(malformed());
+    }
+    Ok(oid.to_vec())
+}
+
+/// Reads one DER tag-length-value from the front of `data`, returning the
+/// tag byte, the value bytes, and whatever trails the value.
+fn read_tlv(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let first_len_byte = *data.get(1)? as usize;
+
+    let (len, header_len) = if first_len_byte & 0x80 == 0 {
+        (first_len_byte, 2)
+    } else {
+        let num_octets = first_len_byte & 0x7f;
+        if num_octets == 0 || num_octets > 4 {
+            return None;
+        }
+        let len_bytes = data.get(2..2 + num_octets)?;
+        let mut len = 0usize;
+        for b in len_bytes {
+            len = (len << 8) | (*b as usize);
+        }
+        (len, 2 + num_octets)
+    };
+
+    let value = data.get(header_len..header_len + len)?;
+    let rest = &data[header_len + len..];
+    Some((tag, value, rest))
+}
@@ -6,11 +6,22 @@ use std::future::Future;
 use std::io;
 use std::pin::Pin;
 use std::result;
+use std::str;
+use std::sync::Arc;
 use std::task::Context;
 use std::task::Poll;
 
 pub mod async_as_sync;
+mod base64;
+mod channel_binding;
+pub mod identity;
 pub mod runtime;
+pub mod session_cache;
+mod sha256;
+mod sha512;
+
+pub use identity::Identity;
+pub use session_cache::{LruSessionCache, SessionCache};
 
 use runtime::{AsyncRead, AsyncWrite};
 
@@ -73,12 +84,30 @@ pub enum CertificateFormat {
     PEM,
 }
 
+/// Controls whether and how an acceptor asks connecting clients for a
+/// certificate.
+pub enum ClientAuth {
+    /// Do not request a client certificate.
+    None,
+    /// Request a client certificate, but allow anonymous (certificate-less)
+    /// clients through; any certificate that is presented is still
+    /// validated against the configured root store.
+    Optional,
+    /// Require a valid client certificate, verified against the configured
+    /// root store; fail the handshake if none is presented.
+    Required,
+}
+
 // X.509 certificate
 pub struct Certificate {
     pub bytes: Vec<u8>,
     pub format: CertificateFormat,
 }
 
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+const PEM_LINE_LEN: usize = 64;
+
 impl Certificate {
     pub fn from_der(der: Vec<u8>) -> Certificate {
         Certificate {
@@ -87,28 +116,104 @@ impl Certificate {
         }
     }
 
-    pub fn into_der(self) -> Option<Vec<u8>> {
-        // TODO: there are methods to convert PEM->DER which might be used here
+    pub fn from_pem(pem: Vec<u8>) -> Certificate {
+        Certificate {
+            bytes: pem,
+            format: CertificateFormat::PEM,
+        }
+    }
+
+    /// Converts to DER, decoding from PEM if necessary.
+    ///
+    /// If `self` is a PEM bundle of several certificates, only the first
+    /// one is decoded.
+    pub fn into_der(self) -> Result<Vec<u8>> {
         match self.format {
-            CertificateFormat::DER => Some(self.bytes),
-            _ => None,
+            CertificateFormat::DER => Ok(self.bytes),
+            CertificateFormat::PEM => {
+                let pem = str::from_utf8(&self.bytes)
+                    .map_err(|_| Error::new_other("PEM certificate is not valid UTF-8"))?;
+                let body = first_pem_block(pem, PEM_BEGIN, PEM_END)
+                    .ok_or_else(|| Error::new_other("PEM certificate armor not found"))?;
+                base64::decode(body)
+            }
         }
     }
-    pub fn into_pem(self) -> Option<Vec<u8>> {
-        // TODO: there are methods to convert DER->PEM which might be used here
+
+    /// Converts to PEM, encoding from DER if necessary.
+    ///
+    /// If `self` is already a PEM bundle of several certificates, all of
+    /// them are preserved as-is.
+    pub fn into_pem(self) -> Result<Vec<u8>> {
         match self.format {
-            CertificateFormat::PEM => Some(self.bytes),
-            _ => None,
+            CertificateFormat::PEM => Ok(self.bytes),
+            CertificateFormat::DER => {
+                let encoded = base64::encode(&self.bytes);
+                let mut pem = String::with_capacity(PEM_BEGIN.len() + PEM_END.len() + encoded.len() + 16);
+                pem.push_str(PEM_BEGIN);
+                pem.push('\n');
+                for line in encoded.as_bytes().chunks(PEM_LINE_LEN) {
+                    pem.push_str(str::from_utf8(line).unwrap());
+                    pem.push('\n');
+                }
+                pem.push_str(PEM_END);
+                pem.push('\n');
+                Ok(pem.into_bytes())
+            }
         }
     }
 }
 
+/// Finds the body of the first PEM block delimited by `begin`/`end`
+/// armor, ignoring any surrounding whitespace.
+fn first_pem_block<'a>(pem: &'a str, begin: &str, end: &str) -> Option<&'a str> {
+    let start = pem.find(begin)? + begin.len();
+    let rest = &pem[start..];
+    let finish = rest.find(end)?;
+    Some(&rest[..finish])
+}
+
 pub trait TlsStreamImpl<S>:
     AsyncRead + AsyncWrite + Unpin + fmt::Debug + Send + Sync + 'static
 {
     /// Get negotiated ALPN protocol.
     fn get_alpn_protocol(&self) -> Option<Vec<u8>>;
 
+    /// Gets the peer's certificate chain (leaf first), in DER form, after a
+    /// successful handshake. Returns `None` if the peer presented no
+    /// certificate.
+    ///
+    /// The default returns `None` unconditionally; backends override it to
+    /// surface the chain their TLS implementation retains post-handshake.
+    fn get_peer_certificates(&self) -> Option<Vec<Certificate>> {
+        None
+    }
+
+    /// Computes the `tls-server-end-point` channel binding (RFC 5929) from
+    /// the peer's leaf certificate, for use with SASL/SCRAM `-PLUS`
+    /// authentication. Returns `Ok(None)` when the peer presented no
+    /// certificate.
+    ///
+    /// The default implementation derives this generically from
+    /// [`get_peer_certificates`](Self::get_peer_certificates); backends only
+    /// need to override it if they have a cheaper native path.
+    fn tls_server_end_point(&self) -> Result<Option<Vec<u8>>> {
+        let leaf = match self.get_peer_certificates().and_then(|certs| certs.into_iter().next()) {
+            Some(leaf) => leaf,
+            None => return Ok(None),
+        };
+        let der = leaf.into_der()?;
+        channel_binding::tls_server_end_point(&der).map(Some)
+    }
+
+    /// Returns the first TLS Finished message sent on this connection, for
+    /// the `tls-unique` channel binding (RFC 5929). Backends that cannot
+    /// expose this (for example under TLS 1.3, where `tls-unique` is not
+    /// well-defined) should return `Ok(None)` rather than erroring.
+    fn tls_unique(&self) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
     fn get_mut(&mut self) -> &mut S;
 
     fn get_ref(&self) -> &S;
@@ -142,6 +247,18 @@ impl<S: 'static> TlsStream<S> {
     pub fn get_alpn_protocol(&self) -> Option<Vec<u8>> {
         self.0.get_alpn_protocol()
     }
+
+    pub fn get_peer_certificates(&self) -> Option<Vec<Certificate>> {
+        self.0.get_peer_certificates()
+    }
+
+    pub fn tls_server_end_point(&self) -> Result<Option<Vec<u8>>> {
+        self.0.tls_server_end_point()
+    }
+
+    pub fn tls_unique(&self) -> Result<Option<Vec<u8>>> {
+        self.0.tls_unique()
+    }
 }
 
 impl<S> AsyncRead for TlsStream<S> {
@@ -194,6 +311,38 @@ pub trait TlsConnectorBuilder: Sized + Sync + Send + 'static {
 
     fn add_root_certificate(&mut self, cert: Certificate) -> Result<&mut Self>;
 
+    /// Sets the certificate (and private key) the client presents to the
+    /// server, for mutual TLS.
+    ///
+    /// The default errors out; backends override it to translate `identity`
+    /// into their native client-certificate configuration.
+    fn set_identity(&mut self, _identity: Identity) -> Result<&mut Self> {
+        Err(Error::new_other(
+            "client identity is not supported by this backend",
+        ))
+    }
+
+    /// Opts into sending 0-RTT early data on [`TlsConnector::connect_with_early_data`]
+    /// when the backend and a cached session ticket permit it. Backends
+    /// without 0-RTT support may ignore this and always perform a normal
+    /// handshake.
+    ///
+    /// The default ignores the flag, which is the correct behavior for a
+    /// backend without 0-RTT support.
+    fn set_enable_early_data(&mut self, _enable: bool) -> Result<()> {
+        Ok(())
+    }
+
+    /// Sets the session-ticket cache used to resume previous sessions with
+    /// the same host, keyed by the `domain` passed to
+    /// [`TlsConnector::connect`]. A prerequisite for 0-RTT early data.
+    ///
+    /// The default ignores the cache; backends override it to map `cache`
+    /// onto their native session-resumption storage.
+    fn set_session_cache(&mut self, _cache: Arc<dyn SessionCache>) -> Result<()> {
+        Ok(())
+    }
+
     fn build(self) -> Result<Self::Connector>;
 }
 
@@ -214,6 +363,52 @@ pub trait TlsConnector: Sized + Sync + Send + 'static {
     ) -> Pin<Box<dyn Future<Output = Result<TlsStream<S>>> + Send + 'a>>
     where
         S: AsyncRead + AsyncWrite + fmt::Debug + Unpin + Send + Sync + 'static;
+
+    /// Like [`connect`](Self::connect), but additionally offers `early_data`
+    /// to send as 0-RTT data in the first flight, if
+    /// `TlsConnectorBuilder::set_enable_early_data` was set and the backend
+    /// has a cached session ticket that permits it.
+    ///
+    /// If the server rejects 0-RTT, the implementation must transparently
+    /// re-send `early_data` once the full handshake completes, so the
+    /// returned `TlsStream` behaves identically to a stream from `connect`
+    /// either way. Backends without 0-RTT support perform a normal
+    /// handshake and then write `early_data` themselves.
+    ///
+    /// The default always performs a normal handshake and then writes
+    /// `early_data` over the established connection, which is the correct
+    /// behavior for a backend that does not implement 0-RTT.
+    fn connect_with_early_data<'a, S>(
+        &'a self,
+        domain: &'a str,
+        stream: S,
+        early_data: &'a [u8],
+    ) -> Pin<Box<dyn Future<Output = Result<TlsStream<S>>> + Send + 'a>>
+    where
+        S: AsyncRead + AsyncWrite + fmt::Debug + Unpin + Send + Sync + 'static,
+    {
+        Box::pin(async move {
+            let mut stream = self.connect(domain, stream).await?;
+            write_all(&mut stream, early_data).await?;
+            Ok(stream)
+        })
+    }
+}
+
+/// Writes all of `data` to `stream`, for use by the default
+/// [`TlsConnector::connect_with_early_data`] implementation.
+async fn write_all<S: 'static>(stream: &mut TlsStream<S>, mut data: &[u8]) -> Result<()> {
+    while !data.is_empty() {
+        let n = std::future::poll_fn(|cx| Pin::new(&mut *stream).poll_write(cx, data)).await?;
+        if n == 0 {
+            return Err(Error::from(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "failed to write early data",
+            )));
+        }
+        data = &data[n..];
+    }
+    Ok(())
 }
 
 /// A builder for `TlsAcceptor`s.
@@ -229,6 +424,39 @@ pub trait TlsAcceptorBuilder: Sized + Sync + Send + 'static {
 
     fn underlying_mut(&mut self) -> &mut Self::Underlying;
 
+    /// Sets the server's certificate chain and private key, making the
+    /// acceptor usable. Backends translate the neutral `Identity` into
+    /// their own native configuration.
+    ///
+    /// The default errors out; a backend must override this before its
+    /// acceptor can be built, since there is otherwise no native identity
+    /// to hand the underlying implementation.
+    fn set_identity(&mut self, _identity: Identity) -> Result<&mut Self> {
+        Err(Error::new_other(
+            "server identity is not supported by this backend",
+        ))
+    }
+
+    /// Configures whether client certificates are requested and, if so,
+    /// verifies them against `roots`. `ClientAuth::Required` fails the
+    /// handshake when no valid client certificate is presented;
+    /// `ClientAuth::Optional` allows anonymous clients through but still
+    /// validates any certificate that is presented.
+    ///
+    /// The default accepts `ClientAuth::None` as a no-op (the handshake
+    /// already behaves that way without any configuration), but errors out
+    /// for `Optional`/`Required` rather than silently skipping client
+    /// verification, since a backend without support for this must not be
+    /// mistaken for one that enforces it.
+    fn set_client_auth(&mut self, mode: ClientAuth, _roots: Vec<Certificate>) -> Result<()> {
+        match mode {
+            ClientAuth::None => Ok(()),
+            ClientAuth::Optional | ClientAuth::Required => Err(Error::new_other(
+                "client certificate authentication is not supported by this backend",
+            )),
+        }
+    }
+
     fn build(self) -> Result<Self::Acceptor>;
 }
 
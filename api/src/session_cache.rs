@@ -0,0 +1,79 @@
+//! A neutral session-ticket cache, so repeat connections to the same host
+//! can resume a previous TLS session instead of performing a full
+//! handshake.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Storage for TLS session tickets, keyed by the `domain` passed to
+/// `TlsConnector::connect`. Each backend maps its own native resumption
+/// storage onto this trait.
+///
+/// Implementations must be safe to share across many concurrent
+/// connections.
+pub trait SessionCache: Send + Sync {
+    fn get(&self, key: &str) -> Option<Vec<u8>>;
+
+    fn put(&self, key: &str, value: Vec<u8>);
+}
+
+struct LruInner {
+    entries: HashMap<String, Vec<u8>>,
+    // Most-recently-used key at the back.
+    order: VecDeque<String>,
+}
+
+impl LruInner {
+    fn touch(&mut self, key: &str) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            let key = self.order.remove(pos).unwrap();
+            self.order.push_back(key);
+        }
+    }
+}
+
+/// A default, in-memory, least-recently-used `SessionCache`.
+pub struct LruSessionCache {
+    capacity: usize,
+    inner: Mutex<LruInner>,
+}
+
+impl LruSessionCache {
+    /// Creates a cache holding at most `capacity` session tickets, evicting
+    /// the least-recently-used entry once full.
+    pub fn with_capacity(capacity: usize) -> LruSessionCache {
+        LruSessionCache {
+            capacity,
+            inner: Mutex::new(LruInner {
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+            }),
+        }
+    }
+}
+
+impl SessionCache for LruSessionCache {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let mut inner = self.inner.lock().unwrap();
+        let value = inner.entries.get(key).cloned();
+        if value.is_some() {
+            inner.touch(key);
+        }
+        value
+    }
+
+    fn put(&self, key: &str, value: Vec<u8>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.insert(key.to_owned(), value).is_none() {
+            inner.order.push_back(key.to_owned());
+        } else {
+            inner.touch(key);
+        }
+        while inner.order.len() > self.capacity {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.entries.remove(&oldest);
+            }
+        }
+    }
+}